@@ -0,0 +1,167 @@
+//! Expression-driven derived columns.
+//!
+//! Derived columns let a workload declare extra columns as expressions over the
+//! generated source fields instead of baking them into [`BenchmarkLog`] and the
+//! builder loop. Each column is parsed from the workload JSON once and then
+//! evaluated per row during generation, appending into a builder whose `DType`
+//! is inferred from the expression.
+
+use std::cmp::Ordering;
+
+use serde::Deserialize;
+use vortex::dtype::{DType, Nullability, PType};
+
+use crate::generation::BenchmarkLog;
+
+/// A named column computed from an [`Expr`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct DerivedColumn {
+    /// Column name, used both in the struct schema and in SQL queries.
+    pub name: String,
+    /// The expression evaluated per row to produce the column's values.
+    pub expr: Expr,
+}
+
+/// A scalar expression over a row's source fields.
+///
+/// Boolean-valued variants (predicates, comparisons, combinators) infer a
+/// `Bool` output `DType`; the integer [`Expr::Arith`] transform infers `I64`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Expr {
+    /// `contains(field, value)` over a string field.
+    Contains { field: String, value: String },
+    /// `starts_with(field, value)` over a string field.
+    StartsWith { field: String, value: String },
+    Eq { field: String, value: Literal },
+    Ne { field: String, value: Literal },
+    Lt { field: String, value: Literal },
+    Le { field: String, value: Literal },
+    Gt { field: String, value: Literal },
+    Ge { field: String, value: Literal },
+    And { exprs: Vec<Expr> },
+    Or { exprs: Vec<Expr> },
+    Not { expr: Box<Expr> },
+    /// Integer transform `field <arith> value` over a numeric field.
+    Arith {
+        field: String,
+        arith: Arith,
+        value: i64,
+    },
+}
+
+/// A literal operand, matched by JSON shape (integer vs. string).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Literal {
+    Int(i64),
+    Str(String),
+}
+
+/// Integer arithmetic operators for [`Expr::Arith`].
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Arith {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+/// A computed value: either boolean or integer, matching the column's dtype.
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+}
+
+impl Expr {
+    /// The `DType` a column built from this expression will have.
+    pub fn output_dtype(&self) -> DType {
+        match self {
+            Expr::Arith { .. } => DType::Primitive(PType::I64, Nullability::NonNullable),
+            _ => DType::Bool(Nullability::NonNullable),
+        }
+    }
+
+    /// Evaluate the expression against a single generated row.
+    pub fn eval(&self, row: &BenchmarkLog) -> anyhow::Result<Value> {
+        let value = match self {
+            Expr::Contains { field, value } => Value::Bool(str_field(row, field)?.contains(value)),
+            Expr::StartsWith { field, value } => {
+                Value::Bool(str_field(row, field)?.starts_with(value))
+            }
+            Expr::Eq { field, value } => Value::Bool(compare(row, field, value)? == Ordering::Equal),
+            Expr::Ne { field, value } => Value::Bool(compare(row, field, value)? != Ordering::Equal),
+            Expr::Lt { field, value } => Value::Bool(compare(row, field, value)? == Ordering::Less),
+            Expr::Le { field, value } => {
+                Value::Bool(compare(row, field, value)? != Ordering::Greater)
+            }
+            Expr::Gt { field, value } => {
+                Value::Bool(compare(row, field, value)? == Ordering::Greater)
+            }
+            Expr::Ge { field, value } => Value::Bool(compare(row, field, value)? != Ordering::Less),
+            Expr::And { exprs } => {
+                let mut acc = true;
+                for e in exprs {
+                    acc &= e.eval_bool(row)?;
+                }
+                Value::Bool(acc)
+            }
+            Expr::Or { exprs } => {
+                let mut acc = false;
+                for e in exprs {
+                    acc |= e.eval_bool(row)?;
+                }
+                Value::Bool(acc)
+            }
+            Expr::Not { expr } => Value::Bool(!expr.eval_bool(row)?),
+            Expr::Arith {
+                field,
+                arith,
+                value,
+            } => Value::Int(apply_arith(int_field(row, field)?, *arith, *value)?),
+        };
+        Ok(value)
+    }
+
+    fn eval_bool(&self, row: &BenchmarkLog) -> anyhow::Result<bool> {
+        match self.eval(row)? {
+            Value::Bool(b) => Ok(b),
+            Value::Int(_) => Err(anyhow::anyhow!("expected a boolean expression")),
+        }
+    }
+}
+
+fn str_field<'a>(row: &'a BenchmarkLog, name: &str) -> anyhow::Result<&'a str> {
+    row.field_str(name)
+        .ok_or_else(|| anyhow::anyhow!("unknown string field `{name}`"))
+}
+
+fn int_field(row: &BenchmarkLog, name: &str) -> anyhow::Result<i64> {
+    row.field_int(name)
+        .ok_or_else(|| anyhow::anyhow!("unknown integer field `{name}`"))
+}
+
+/// Order a field against a literal, dispatching on the literal's type.
+fn compare(row: &BenchmarkLog, field: &str, value: &Literal) -> anyhow::Result<Ordering> {
+    match value {
+        Literal::Int(v) => Ok(int_field(row, field)?.cmp(v)),
+        Literal::Str(v) => Ok(str_field(row, field)?.cmp(v.as_str())),
+    }
+}
+
+fn apply_arith(lhs: i64, op: Arith, rhs: i64) -> anyhow::Result<i64> {
+    let overflow = || anyhow::anyhow!("overflow in derived column");
+    let value = match op {
+        Arith::Add => lhs.checked_add(rhs).ok_or_else(overflow)?,
+        Arith::Sub => lhs.checked_sub(rhs).ok_or_else(overflow)?,
+        Arith::Mul => lhs.checked_mul(rhs).ok_or_else(overflow)?,
+        Arith::Div | Arith::Mod if rhs == 0 => {
+            return Err(anyhow::anyhow!("division by zero in derived column"));
+        }
+        Arith::Div => lhs / rhs,
+        Arith::Mod => lhs % rhs,
+    };
+    Ok(value)
+}
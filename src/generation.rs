@@ -20,6 +20,30 @@ pub struct BenchmarkLog {
     pub metadata: String,
 }
 
+impl BenchmarkLog {
+    /// Read a string-typed source field by name, for use by derived-column
+    /// expressions. Returns `None` for unknown or non-string fields.
+    pub fn field_str(&self, name: &str) -> Option<&str> {
+        match name {
+            "message" => Some(self.message),
+            "country" => Some(self.country),
+            "metadata" => Some(&self.metadata),
+            _ => None,
+        }
+    }
+
+    /// Read an integer-typed source field by name, widened to `i64`. Returns
+    /// `None` for unknown or non-integer fields.
+    pub fn field_int(&self, name: &str) -> Option<i64> {
+        match name {
+            "id" => Some(self.id as i64),
+            "severity" => Some(self.severity as i64),
+            "timestamp" => Some(self.timestamp as i64),
+            _ => None,
+        }
+    }
+}
+
 const MESSAGES: &[&str] = &[
     "The research team discovered a new species of deep-sea creature while conducting experiments near hydrothermal vents in the dark ocean depths.",
     "The research facility analyzed samples from ancient artifacts, revealing breakthrough findings about civilizations lost to the depths of time.",
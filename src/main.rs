@@ -1,5 +1,8 @@
+mod derived;
 mod generation;
+mod workload;
 
+use std::collections::HashMap;
 use std::pin::pin;
 use std::sync::Arc;
 
@@ -11,8 +14,9 @@ use datafusion::prelude::SessionContext;
 use futures::StreamExt;
 use tempfile::tempdir;
 use tokio::fs::OpenOptions;
-use vortex::IntoArray;
-use vortex::arrays::StructArray;
+use tracing::Instrument;
+use vortex::{ArrayRef, IntoArray};
+use vortex::arrays::{DictArray, StructArray};
 use vortex::builders::ArrayBuilderExt;
 use vortex::builders::builder_with_capacity;
 use vortex::dtype::{DType, Nullability, PType, StructFields};
@@ -23,12 +27,172 @@ use vortex::validity::Validity;
 use vortex_datafusion::VortexFormat;
 
 use generation::generate_logs;
+use workload::{QueryResult, RunRecord, Workload};
 
-const CHUNK_SIZE: usize = 100_000;
 const RUNS_PER_QUERY: u32 = 10;
 
+/// Leading runs discarded as warm-up before computing the latency distribution.
+const WARMUP_RUNS: usize = 2;
+
+/// Target working-set budget for a single chunk's builders, in bytes.
+const MAX_CHUNK_BYTES: usize = 16 * 1024 * 1024;
+
+/// Smallest chunk size we'll ever pick, to avoid tiny row groups.
+const MIN_CHUNK_SIZE: usize = 8192;
+
+/// Average byte length assumed for variable-width `Utf8` fields (`message`,
+/// `metadata`) when estimating per-row size.
+const AVG_UTF8_LEN: usize = 160;
+
+/// Derive a chunk (row-group) size from the run's shape rather than a fixed
+/// constant. We target [`MAX_CHUNK_BYTES`] of builder working set per chunk,
+/// then clamp so every worker thread gets at least one chunk and floor to
+/// [`MIN_CHUNK_SIZE`].
+fn compute_chunk_size(num_rows: u64, field_dtypes: &[DType]) -> usize {
+    let row_bytes: usize = field_dtypes.iter().map(estimated_dtype_bytes).sum::<usize>().max(1);
+
+    // Rows that fit inside the per-chunk byte budget.
+    let by_budget = (MAX_CHUNK_BYTES / row_bytes).max(1);
+
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+
+    // Ensure `num_rows / chunk_size >= threads`, i.e. at least one chunk per
+    // thread, by capping the chunk size when the budget alone would be coarser.
+    let by_threads = (num_rows as usize / threads.max(1)).max(1);
+
+    by_budget.min(by_threads).max(MIN_CHUNK_SIZE)
+}
+
+/// Estimated in-memory byte size of a single value of `dtype`, used only for
+/// sizing chunks. Variable-width `Utf8` is charged [`AVG_UTF8_LEN`] bytes.
+fn estimated_dtype_bytes(dtype: &DType) -> usize {
+    match dtype {
+        DType::Bool(_) => 1,
+        DType::Primitive(ptype, _) => ptype.byte_width(),
+        DType::Utf8(_) | DType::Binary(_) => AVG_UTF8_LEN,
+        _ => std::mem::size_of::<u64>(),
+    }
+}
+
+/// Command-line configuration for a benchmark run.
+struct Cli {
+    /// Workload JSON files to run, in order.
+    workloads: Vec<String>,
+    /// Dashboard endpoint to POST results to, if any.
+    dashboard_url: Option<String>,
+    /// API key for the dashboard (paired with `dashboard_url`).
+    api_key: Option<String>,
+    /// Free-form reason tagged onto uploaded runs.
+    reason: Option<String>,
+    /// If set, install a flamegraph tracing layer writing folded stack samples
+    /// to this path. Also honoured via the `VAGG_FLAMEGRAPH` env var.
+    flamegraph: Option<String>,
+}
+
+impl Cli {
+    fn parse() -> anyhow::Result<Self> {
+        let mut workloads = Vec::new();
+        let mut dashboard_url = None;
+        let mut api_key = None;
+        let mut reason = None;
+        let mut flamegraph = std::env::var("VAGG_FLAMEGRAPH").ok();
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--dashboard-url" => {
+                    dashboard_url = Some(expect_value(&mut args, "--dashboard-url")?);
+                }
+                "--api-key" => {
+                    api_key = Some(expect_value(&mut args, "--api-key")?);
+                }
+                "--reason" => {
+                    reason = Some(expect_value(&mut args, "--reason")?);
+                }
+                "--flamegraph" => {
+                    flamegraph = Some(expect_value(&mut args, "--flamegraph")?);
+                }
+                other if other.starts_with("--") => {
+                    return Err(vortex_err!("unknown flag {other}").into());
+                }
+                path => workloads.push(path.to_string()),
+            }
+        }
+
+        if workloads.is_empty() {
+            workloads.push("workloads/default.json".to_string());
+        }
+
+        // Uploading requires both halves; a lone flag would silently drop a
+        // requested upload, so reject it up front.
+        if dashboard_url.is_some() != api_key.is_some() {
+            return Err(vortex_err!(
+                "--dashboard-url and --api-key must be provided together"
+            )
+            .into());
+        }
+
+        Ok(Self {
+            workloads,
+            dashboard_url,
+            api_key,
+            reason,
+            flamegraph,
+        })
+    }
+}
+
+/// Install a `tracing` subscriber with a [`tracing_flame`] layer that writes
+/// folded stack samples to `path`. The returned guard flushes the samples on
+/// drop; render them with e.g. `inferno-flamegraph < path > flamegraph.svg`.
+fn init_profiling(path: &str) -> anyhow::Result<impl Drop> {
+    use tracing_subscriber::prelude::*;
+
+    let (flame_layer, guard) = tracing_flame::FlameLayer::with_file(path)?;
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer())
+        .with(flame_layer)
+        .init();
+    Ok(guard)
+}
+
+fn expect_value(
+    args: &mut impl Iterator<Item = String>,
+    flag: &str,
+) -> anyhow::Result<String> {
+    args.next()
+        .ok_or_else(|| vortex_err!("missing value for {flag}").into())
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse()?;
+
+    // Keep the flush guard alive for the whole run; it writes the folded stack
+    // samples out when dropped at the end of `main`.
+    let _flame_guard = match &cli.flamegraph {
+        Some(path) => Some(init_profiling(path)?),
+        None => None,
+    };
+
+    for path in &cli.workloads {
+        let workload = Workload::from_file(path)?;
+        let record = run_workload(&cli, &workload).await?;
+
+        if let (Some(url), Some(key)) = (&cli.dashboard_url, &cli.api_key) {
+            record.upload(url, key).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Build the dataset described by `workload`, register it, and run its queries.
+async fn run_workload(cli: &Cli, workload: &Workload) -> anyhow::Result<RunRecord> {
+    workload.schema.validate()?;
+
     let temp_dir = tempdir()?;
 
     let filepath = temp_dir.path().join("a.vortex");
@@ -40,9 +204,13 @@ async fn main() -> anyhow::Result<()> {
         .open(&filepath)
         .await?;
 
-    VortexWriteOptions::default()
-        .write(f, row_array_stream(100_000_000))
-        .await?;
+    async {
+        VortexWriteOptions::default()
+            .write(f, row_array_stream(workload.num_rows, &workload.schema))
+            .await
+    }
+    .instrument(tracing::info_span!("write_dataset", workload = %workload.name))
+    .await?;
 
     let ctx = SessionContext::new();
     let format = Arc::new(VortexFormat::default());
@@ -60,63 +228,177 @@ async fn main() -> anyhow::Result<()> {
 
     ctx.register_table("vortex_tbl", listing_table as _)?;
 
-    run_queries(&ctx).await
+    let results = run_queries(&ctx, workload)
+        .instrument(tracing::info_span!("run_queries", workload = %workload.name))
+        .await?;
+
+    Ok(RunRecord::new(
+        workload.name.clone(),
+        cli.reason.clone(),
+        results,
+    ))
+}
+
+/// Accumulates `u32` codes for a single dictionary-encoded `Utf8` column.
+///
+/// The source values are drawn from small, fixed `&'static str` pools, so a
+/// [`HashMap`] code table keeps the dictionary tiny while the per-row payload
+/// collapses to a `u32` code.
+struct DictColumn {
+    table: HashMap<&'static str, u32>,
+    values: Vec<&'static str>,
+    codes: Vec<u32>,
+}
+
+impl DictColumn {
+    fn with_capacity(cap: usize) -> Self {
+        Self {
+            table: HashMap::new(),
+            values: Vec::new(),
+            codes: Vec::with_capacity(cap),
+        }
+    }
+
+    fn push(&mut self, value: &'static str) {
+        let code = match self.table.get(value) {
+            Some(code) => *code,
+            None => {
+                let code = self.values.len() as u32;
+                self.table.insert(value, code);
+                self.values.push(value);
+                code
+            }
+        };
+        self.codes.push(code);
+    }
+
+    /// Emit the accumulated codes and unique values as a Vortex dictionary
+    /// array. Its logical dtype is `Utf8`, so the enclosing struct schema is
+    /// unchanged versus the plain encoding.
+    fn finish(self) -> anyhow::Result<ArrayRef> {
+        let mut codes = builder_with_capacity(
+            (&DType::Primitive(PType::U32, Nullability::NonNullable)).into(),
+            self.codes.len(),
+        );
+        for code in self.codes {
+            codes.append_scalar_value(code.into())?;
+        }
+
+        let mut values = builder_with_capacity(
+            (&DType::Utf8(Nullability::NonNullable)).into(),
+            self.values.len(),
+        );
+        for value in self.values {
+            values.append_scalar_value(value.into())?;
+        }
+
+        Ok(DictArray::try_new(codes.finish(), values.finish())?.into_array())
+    }
 }
 
-fn row_array_stream(num_rows: u64) -> impl ArrayStream + Unpin {
-    // Define the DType for the BenchmarkLog struct
-    let field_dtypes = vec![
+fn row_array_stream(
+    num_rows: u64,
+    schema: &workload::SchemaOptions,
+) -> impl ArrayStream + Unpin {
+    // Base columns produced directly by the generator, in builder order.
+    let mut field_names = vec![
+        "id".into(),
+        "message".into(),
+        "country".into(),
+        "severity".into(),
+        "timestamp".into(),
+        "metadata".into(),
+    ];
+    let mut field_dtypes = vec![
         DType::Primitive(PType::U64, Nullability::NonNullable),
         DType::Utf8(Nullability::NonNullable),
-        DType::Bool(Nullability::NonNullable),
-        DType::Bool(Nullability::NonNullable),
         DType::Utf8(Nullability::NonNullable),
         DType::Primitive(PType::I32, Nullability::NonNullable),
         DType::Primitive(PType::U64, Nullability::NonNullable),
         DType::Utf8(Nullability::NonNullable),
     ];
-    let benchmark_log_struct_fields: Arc<StructFields> = StructFields::new(
-        vec![
-            "id".into(),
-            "message".into(),
-            "message_matches_research".into(),
-            "message_matches_team".into(),
-            "country".into(),
-            "severity".into(),
-            "timestamp".into(),
-            "metadata".into(),
-        ]
-        .into(),
-        field_dtypes.clone(),
-    )
-    .into();
+
+    // Derived columns, appended after the base columns with dtypes inferred
+    // from their expressions.
+    let derived_exprs: Vec<derived::Expr> = schema
+        .derived_columns
+        .iter()
+        .map(|col| {
+            field_names.push(col.name.as_str().into());
+            field_dtypes.push(col.expr.output_dtype());
+            col.expr.clone()
+        })
+        .collect();
+
+    let benchmark_log_struct_fields: Arc<StructFields> =
+        StructFields::new(field_names.into(), field_dtypes.clone()).into();
     let benchmark_log_dtype = DType::Struct(
         benchmark_log_struct_fields.clone(),
         Nullability::NonNullable,
     );
 
+    let chunk_size = compute_chunk_size(num_rows, &field_dtypes);
+
+    // Which of the low-cardinality string columns to dictionary-encode. These
+    // are the only columns whose values come from a fixed `&'static str` pool.
+    let dict = |name: &str| schema.dictionary_columns.iter().any(|c| c == name);
+    let message_dict = dict("message");
+    let country_dict = dict("country");
+
     // Create a stream that emits batches of documents as StructArrays.
     let stream = stream! {
-        let mut chunks = pin!(generate_logs(num_rows).chunks(CHUNK_SIZE));
+        let mut chunks = pin!(generate_logs(num_rows).chunks(chunk_size));
         while let Some(chunk) = chunks.next().await {
-            let mut builders = field_dtypes.iter().map(|dtype| {
-                builder_with_capacity(dtype.into(), CHUNK_SIZE)
+            // Plain builders for every field; dictionary columns are left as
+            // `None` here and written through a `DictColumn` instead.
+            let mut builders = field_dtypes.iter().enumerate().map(|(i, dtype)| {
+                let is_dict = (i == 1 && message_dict) || (i == 2 && country_dict);
+                (!is_dict).then(|| builder_with_capacity(dtype.into(), chunk_size))
             }).collect::<Vec<_>>();
+            let mut message_col = message_dict.then(|| DictColumn::with_capacity(chunk_size));
+            let mut country_col = country_dict.then(|| DictColumn::with_capacity(chunk_size));
 
             let chunk_len = chunk.len();
             for row in chunk {
-                builders[0].append_scalar_value(row.id.into())?;
-                builders[1].append_scalar_value(row.message.into())?;
-                builders[2].append_scalar_value(row.message_matches_research.into())?;
-                builders[3].append_scalar_value(row.message_matches_team.into())?;
-                builders[4].append_scalar_value(row.country.into())?;
-                builders[5].append_scalar_value(row.severity.into())?;
-                builders[6].append_scalar_value(row.timestamp.into())?;
-                builders[7].append_scalar_value(row.metadata.into())?;
+                // Evaluate derived columns first, while the whole row is owned;
+                // they occupy builder indices 6.. in declaration order.
+                for (k, expr) in derived_exprs.iter().enumerate() {
+                    let value = expr.eval(&row)?;
+                    let builder = builders[6 + k].as_mut().unwrap();
+                    match value {
+                        derived::Value::Bool(b) => builder.append_scalar_value(b.into())?,
+                        derived::Value::Int(v) => builder.append_scalar_value(v.into())?,
+                    }
+                }
+
+                builders[0].as_mut().unwrap().append_scalar_value(row.id.into())?;
+                if let Some(col) = message_col.as_mut() {
+                    col.push(row.message);
+                } else {
+                    builders[1].as_mut().unwrap().append_scalar_value(row.message.into())?;
+                }
+                if let Some(col) = country_col.as_mut() {
+                    col.push(row.country);
+                } else {
+                    builders[2].as_mut().unwrap().append_scalar_value(row.country.into())?;
+                }
+                builders[3].as_mut().unwrap().append_scalar_value(row.severity.into())?;
+                builders[4].as_mut().unwrap().append_scalar_value(row.timestamp.into())?;
+                builders[5].as_mut().unwrap().append_scalar_value(row.metadata.into())?;
+            }
+
+            let mut children: Vec<ArrayRef> = Vec::with_capacity(builders.len());
+            for (i, builder) in builders.into_iter().enumerate() {
+                let array = match builder {
+                    Some(mut b) => b.finish(),
+                    None if i == 1 => message_col.take().unwrap().finish()?,
+                    None => country_col.take().unwrap().finish()?,
+                };
+                children.push(array);
             }
 
             yield Ok(StructArray::try_new_with_dtype(
-                builders.into_iter().map(|mut b| b.finish()).collect(),
+                children,
                 benchmark_log_struct_fields.clone(),
                 chunk_len,
                 Validity::NonNullable,
@@ -128,87 +410,32 @@ fn row_array_stream(num_rows: u64) -> impl ArrayStream + Unpin {
     ArrayStreamAdapter::new(benchmark_log_dtype, stream.boxed())
 }
 
-async fn run_queries(ctx: &SessionContext) -> anyhow::Result<()> {
-    let queries = vec![
-        (
-            "count-filter",
-            "SELECT COUNT(*) FROM vortex_tbl WHERE message_matches_team = true",
-        ),
-        ("count-nofilter", "SELECT COUNT(id) FROM vortex_tbl"),
-        (
-            "count-filter",
-            "SELECT COUNT(id) FROM vortex_tbl WHERE message_matches_team = true",
-        ),
-        (
-            "cardinality",
-            "SELECT COUNT(DISTINCT severity) FROM vortex_tbl WHERE message_matches_research = true",
-        ),
-        (
-            "bucket-string-nofilter",
-            "SELECT country, COUNT(*) FROM vortex_tbl GROUP BY country ORDER BY country",
-        ),
-        (
-            "bucket-string-filter",
-            "SELECT country, COUNT(*) FROM vortex_tbl WHERE message_matches_research = true GROUP BY country ORDER BY country",
-        ),
-        (
-            "bucket-numeric-nofilter",
-            "SELECT severity, COUNT(*) FROM vortex_tbl GROUP BY severity ORDER BY severity",
-        ),
-        (
-            "bucket-numeric-filter",
-            "SELECT severity, COUNT(*) FROM vortex_tbl WHERE message_matches_research = true GROUP BY severity ORDER BY severity",
-        ),
-        (
-            "top_n-compound",
-            "SELECT * FROM vortex_tbl WHERE message_matches_research = true AND country = 'Canada' ORDER BY severity, timestamp LIMIT 10",
-        ),
-        (
-            "top_n-numeric-lowcard",
-            "SELECT * FROM vortex_tbl WHERE message_matches_research = true AND country = 'Canada' ORDER BY severity LIMIT 10",
-        ),
-        (
-            "top_n-numeric-highcard",
-            "SELECT * FROM vortex_tbl WHERE message_matches_research = true AND country = 'Canada' ORDER BY timestamp LIMIT 10",
-        ),
-        (
-            "top_n-string",
-            "SELECT * FROM vortex_tbl WHERE message_matches_research = true AND country = 'Canada' ORDER BY country LIMIT 10",
-        ),
-        (
-            "filtered-lowcard",
-            "SELECT * FROM vortex_tbl WHERE message_matches_research = true AND country = 'Canada' AND severity < 3 LIMIT 10",
-        ),
-        (
-            "filtered-highcard",
-            // '2020-10-02' as a unix timestamp.
-            "SELECT * FROM vortex_tbl WHERE message_matches_research = true AND country = 'Canada' AND timestamp >= 1601622000 LIMIT 10",
-        ),
-    ];
-    for (query_type, query) in queries {
-        run_query(ctx, query_type, query).await?;
+async fn run_queries(
+    ctx: &SessionContext,
+    workload: &Workload,
+) -> anyhow::Result<Vec<QueryResult>> {
+    let mut results = Vec::with_capacity(workload.queries.len());
+    for query in &workload.queries {
+        results.push(run_query(ctx, &query.name, &query.sql).await?);
     }
-
-    Ok(())
+    Ok(results)
 }
 
+#[tracing::instrument(skip_all, fields(query = query_type.as_ref()))]
 async fn run_query(
     ctx: &SessionContext,
     query_type: impl AsRef<str>,
     query_string: impl AsRef<str>,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<QueryResult> {
     let query_type = query_type.as_ref();
     let query_string = query_string.as_ref();
 
-    // ctx.sql(&format!("EXPLAIN {query_string}"))
-    //     .await?
-    //     .show()
-    //     .await?;
-
-    let start = std::time::Instant::now();
+    let mut timings_ms = Vec::with_capacity(RUNS_PER_QUERY as usize);
     let mut count = None;
     for _ in 0..RUNS_PER_QUERY {
+        let start = std::time::Instant::now();
         let current_count = ctx.sql(query_string).await?.collect().await?.len();
+        timings_ms.push(start.elapsed().as_millis());
         if let Some(count) = count {
             assert_eq!(
                 count, current_count,
@@ -218,11 +445,18 @@ async fn run_query(
             count = Some(current_count);
         }
     }
+
+    let count = count.unwrap();
+    let latency_ms = workload::Latency::from_samples(timings_ms, WARMUP_RUNS);
     println!(
-        "{query_type}\t{} ms avg\t{} rows\t`{query_string}`",
-        (start.elapsed() / RUNS_PER_QUERY).as_millis(),
-        count.unwrap(),
+        "{query_type}\tmin {} / med {} / p95 {} / max {} ms\t{count} rows\t`{query_string}`",
+        latency_ms.min, latency_ms.median, latency_ms.p95, latency_ms.max,
     );
 
-    Ok(())
+    Ok(QueryResult {
+        name: query_type.to_string(),
+        sql: query_string.to_string(),
+        row_count: count,
+        latency_ms,
+    })
 }
@@ -0,0 +1,212 @@
+//! Workload definitions and run-result reporting for the benchmark harness.
+//!
+//! A workload is a JSON file describing one dataset (its row count and a few
+//! schema toggles) together with a list of named queries to run against it.
+//! Loading workloads from disk means adding or tweaking a benchmark no longer
+//! requires recompiling the harness. After a run, each query produces a
+//! [`QueryResult`]; the whole run is collected into a [`RunRecord`] which can
+//! optionally be POSTed to a results dashboard for cross-commit comparison.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// A single benchmark workload loaded from a JSON file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Workload {
+    /// Human-readable name, also used to tag results.
+    pub name: String,
+    /// Number of rows to generate for the dataset.
+    pub num_rows: u64,
+    /// Per-column schema toggles (e.g. dictionary encoding).
+    #[serde(default)]
+    pub schema: SchemaOptions,
+    /// The queries to run, in order.
+    pub queries: Vec<WorkloadQuery>,
+}
+
+/// Columns that can be dictionary-encoded: those whose values come from a
+/// fixed `&'static str` pool. `metadata` is excluded because each row's JSON
+/// string embeds a high-cardinality `value`, so the whole column does not
+/// dictionary-encode well.
+pub const DICTIONARY_ENCODABLE: &[&str] = &["message", "country"];
+
+/// Output-column schema toggles for a workload's dataset.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SchemaOptions {
+    /// Columns to build as Vortex dictionary-encoded arrays rather than plain
+    /// `Utf8`. Only the low-cardinality string columns `message` and `country`
+    /// are supported (see [`DICTIONARY_ENCODABLE`]).
+    #[serde(default)]
+    pub dictionary_columns: Vec<String>,
+    /// Columns computed from expressions over the source fields, appended
+    /// after the base columns.
+    #[serde(default)]
+    pub derived_columns: Vec<crate::derived::DerivedColumn>,
+}
+
+impl SchemaOptions {
+    /// Reject dictionary-column names that aren't actually wired, so a typo or
+    /// an unsupported column (e.g. `metadata`) fails loudly instead of being
+    /// silently ignored.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for col in &self.dictionary_columns {
+            if !DICTIONARY_ENCODABLE.contains(&col.as_str()) {
+                anyhow::bail!(
+                    "column `{col}` cannot be dictionary-encoded; supported columns: {DICTIONARY_ENCODABLE:?}"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A named query within a workload.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkloadQuery {
+    /// Short stable name used to group results across runs.
+    pub name: String,
+    /// The SQL to execute.
+    pub sql: String,
+}
+
+impl Workload {
+    /// Load a single workload from a JSON file.
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)?;
+        let workload = serde_json::from_slice(&bytes)
+            .map_err(|e| anyhow::anyhow!("failed to parse workload {}: {e}", path.display()))?;
+        Ok(workload)
+    }
+}
+
+/// The measured result of running a single named query.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryResult {
+    /// The query's name from the workload.
+    pub name: String,
+    /// The SQL that was executed.
+    pub sql: String,
+    /// Stable result-row count observed across runs.
+    pub row_count: usize,
+    /// Latency distribution over the timed (post-warm-up) runs.
+    pub latency_ms: Latency,
+}
+
+/// Summary statistics for a query's per-run wall-clock latencies.
+///
+/// Warm-up iterations are discarded before the distribution is computed so
+/// that first-run effects (cold caches, lazy initialization) don't skew the
+/// profile. All durations are milliseconds.
+#[derive(Debug, Clone, Serialize)]
+pub struct Latency {
+    /// Total runs executed, including discarded warm-up iterations.
+    pub runs: usize,
+    /// Number of leading runs discarded as warm-up.
+    pub warmup: usize,
+    pub min: u128,
+    pub median: u128,
+    pub p95: u128,
+    pub max: u128,
+    pub mean: u128,
+    /// The raw post-warm-up samples, in execution order.
+    pub samples: Vec<u128>,
+}
+
+impl Latency {
+    /// Build a distribution from every run's duration, discarding the first
+    /// `warmup` samples. `warmup` is clamped so at least one sample survives.
+    pub fn from_samples(all_ms: Vec<u128>, warmup: usize) -> Self {
+        let runs = all_ms.len();
+        let warmup = warmup.min(runs.saturating_sub(1));
+        let samples: Vec<u128> = all_ms[warmup..].to_vec();
+
+        let mut sorted = samples.clone();
+        sorted.sort_unstable();
+
+        let min = *sorted.first().unwrap_or(&0);
+        let max = *sorted.last().unwrap_or(&0);
+        let median = percentile(&sorted, 50);
+        let p95 = percentile(&sorted, 95);
+        let mean = if samples.is_empty() {
+            0
+        } else {
+            samples.iter().sum::<u128>() / samples.len() as u128
+        };
+
+        Self {
+            runs,
+            warmup,
+            min,
+            median,
+            p95,
+            max,
+            mean,
+            samples,
+        }
+    }
+}
+
+/// Nearest-rank percentile of an already-sorted, non-empty slice.
+fn percentile(sorted: &[u128], pct: usize) -> u128 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// A full benchmark run: every query's result for one workload, tagged with
+/// enough provenance to compare runs across commits.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunRecord {
+    /// The workload's name.
+    pub workload: String,
+    /// Free-form reason supplied on the command line (what changed / why).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// The `HEAD` commit SHA at the time of the run, if resolvable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_sha: Option<String>,
+    /// Per-query results, in workload order.
+    pub results: Vec<QueryResult>,
+}
+
+impl RunRecord {
+    pub fn new(workload: String, reason: Option<String>, results: Vec<QueryResult>) -> Self {
+        Self {
+            workload,
+            reason,
+            commit_sha: current_commit_sha(),
+            results,
+        }
+    }
+
+    /// POST the run to a dashboard, authenticating with a bearer API key.
+    pub async fn upload(&self, dashboard_url: &str, api_key: &str) -> anyhow::Result<()> {
+        let client = reqwest::Client::new();
+        let response = client
+            .post(dashboard_url)
+            .bearer_auth(api_key)
+            .json(self)
+            .send()
+            .await?;
+        response.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Resolve the current `HEAD` commit SHA via `git`, returning `None` if the
+/// command fails (e.g. not a checkout).
+fn current_commit_sha() -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let sha = String::from_utf8(output.stdout).ok()?;
+    Some(sha.trim().to_string())
+}